@@ -2,10 +2,14 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use serde::{Deserialize, Serialize};
+use std::env;
 use std::fs;
 use std::path::PathBuf;
 use tauri::Manager;
 
+const EMBEDDING_MODEL: &str = "text-embedding-ada-002";
+const EMBEDDINGS_URL: &str = "https://api.openai.com/v1/embeddings";
+
 #[derive(Debug, Serialize, Deserialize)]
 struct MemoryItem {
     id: String,
@@ -14,6 +18,8 @@ struct MemoryItem {
     topic: String,
     content: String,
     tags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    embedding: Option<Vec<f32>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -21,6 +27,93 @@ struct MemoryStore {
     memories: Vec<MemoryItem>,
 }
 
+#[derive(Debug, Serialize)]
+struct EmbeddingsRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingsDatum>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingsDatum {
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Serialize)]
+struct MemorySearchResult {
+    memory: MemoryItem,
+    score: f32,
+}
+
+async fn embed_text(text: &str) -> Result<Vec<f32>, String> {
+    let api_key = env::var("OPENAI_API_KEY")
+        .map_err(|_| "OPENAI_API_KEY not found in environment variables".to_string())?;
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(EMBEDDINGS_URL)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Content-Type", "application/json")
+        .json(&EmbeddingsRequest {
+            model: EMBEDDING_MODEL,
+            input: text,
+        })
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send embeddings request: {}", e))?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(format!("OpenAI embeddings API error: {}", error_text));
+    }
+
+    let body: EmbeddingsResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse embeddings response: {}", e))?;
+
+    body.data
+        .into_iter()
+        .next()
+        .map(|d| d.embedding)
+        .ok_or_else(|| "No embedding returned from OpenAI".to_string())
+}
+
+fn normalize(vector: &[f32]) -> Vec<f32> {
+    let magnitude = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if magnitude == 0.0 {
+        return vector.to_vec();
+    }
+    vector.iter().map(|v| v / magnitude).collect()
+}
+
+/// `a` and `b` are expected to already be unit-normalized (via `normalize`)
+/// at store/query time, so the dot product alone is the cosine similarity —
+/// no need to recompute magnitudes on every comparison.
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+fn load_memory_store(file_path: &PathBuf) -> Result<MemoryStore, String> {
+    if !file_path.exists() {
+        return Ok(MemoryStore { memories: vec![] });
+    }
+    let file_contents = fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read memories file: {}", e))?;
+    serde_json::from_str(&file_contents)
+        .map_err(|e| format!("Failed to parse memories file: {}", e))
+}
+
+fn write_memory_store(file_path: &PathBuf, memory_store: &MemoryStore) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(memory_store)
+        .map_err(|e| format!("Failed to serialize memories: {}", e))?;
+    fs::write(file_path, json).map_err(|e| format!("Failed to write memories file: {}", e))
+}
+
 fn get_memories_file_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
     let app_data_dir = app.path_resolver()
         .app_data_dir()
@@ -34,7 +127,7 @@ fn get_memories_file_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
 }
 
 #[tauri::command]
-fn save_memory(
+async fn save_memory(
     app: tauri::AppHandle,
     id: String,
     timestamp: u64,
@@ -44,17 +137,21 @@ fn save_memory(
     tags: Option<Vec<String>>,
 ) -> Result<(), String> {
     let file_path = get_memories_file_path(&app)?;
-    
-    // Load existing memories
-    let mut memory_store = if file_path.exists() {
-        let file_contents = fs::read_to_string(&file_path)
-            .map_err(|e| format!("Failed to read memories file: {}", e))?;
-        serde_json::from_str::<MemoryStore>(&file_contents)
-            .unwrap_or_else(|_| MemoryStore { memories: vec![] })
-    } else {
-        MemoryStore { memories: vec![] }
+    let mut memory_store = load_memory_store(&file_path)?;
+
+    // Embed once at store time so search never has to recompute this per
+    // query. A save is a local file write first and foremost, so an
+    // embeddings failure (missing API key, rate limit, connectivity) must
+    // not lose the memory — leave `embedding` unset and let
+    // `search_memories`'s lazy backfill pick it up later.
+    let embedding = match embed_text(&content).await {
+        Ok(vector) => Some(normalize(&vector)),
+        Err(e) => {
+            println!("Failed to embed memory '{}' at save time, will backfill on search: {}", id, e);
+            None
+        }
     };
-    
+
     // Check if memory with this ID already exists and update it, otherwise add new
     if let Some(existing) = memory_store.memories.iter_mut().find(|m| m.id == id) {
         existing.timestamp = timestamp;
@@ -62,6 +159,7 @@ fn save_memory(
         existing.topic = topic;
         existing.content = content;
         existing.tags = tags;
+        existing.embedding = embedding;
     } else {
         memory_store.memories.push(MemoryItem {
             id,
@@ -70,19 +168,14 @@ fn save_memory(
             topic,
             content,
             tags,
+            embedding,
         });
     }
-    
+
     // Sort by timestamp (newest first)
     memory_store.memories.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-    
-    // Save to file
-    let json = serde_json::to_string_pretty(&memory_store)
-        .map_err(|e| format!("Failed to serialize memories: {}", e))?;
-    fs::write(&file_path, json)
-        .map_err(|e| format!("Failed to write memories file: {}", e))?;
-    
-    Ok(())
+
+    write_memory_store(&file_path, &memory_store)
 }
 
 #[tauri::command]
@@ -93,66 +186,110 @@ fn load_memories(
     limit: Option<usize>,
 ) -> Result<Vec<MemoryItem>, String> {
     let file_path = get_memories_file_path(&app)?;
-    
+
     if !file_path.exists() {
         return Ok(vec![]);
     }
-    
-    let file_contents = fs::read_to_string(&file_path)
-        .map_err(|e| format!("Failed to read memories file: {}", e))?;
-    
-    let memory_store: MemoryStore = serde_json::from_str(&file_contents)
-        .map_err(|e| format!("Failed to parse memories file: {}", e))?;
-    
+
+    let memory_store = load_memory_store(&file_path)?;
     let mut memories = memory_store.memories;
-    
+
     // Filter by agent_type if provided
     if let Some(agent) = agent_type {
         memories.retain(|m| m.agent_type == agent);
     }
-    
+
     // Filter by topic if provided
     if let Some(t) = topic {
         memories.retain(|m| m.topic == t);
     }
-    
+
     // Apply limit if provided
     if let Some(l) = limit {
         memories.truncate(l);
     }
-    
+
     Ok(memories)
 }
 
 #[tauri::command]
 fn delete_memory(app: tauri::AppHandle, memory_id: String) -> Result<(), String> {
     let file_path = get_memories_file_path(&app)?;
-    
+
     if !file_path.exists() {
         return Ok(());
     }
-    
-    let file_contents = fs::read_to_string(&file_path)
-        .map_err(|e| format!("Failed to read memories file: {}", e))?;
-    
-    let mut memory_store: MemoryStore = serde_json::from_str(&file_contents)
-        .map_err(|e| format!("Failed to parse memories file: {}", e))?;
-    
+
+    let mut memory_store = load_memory_store(&file_path)?;
+
     // Remove memory with matching ID
     memory_store.memories.retain(|m| m.id != memory_id);
-    
-    // Save updated memories
-    let json = serde_json::to_string_pretty(&memory_store)
-        .map_err(|e| format!("Failed to serialize memories: {}", e))?;
-    fs::write(&file_path, json)
-        .map_err(|e| format!("Failed to write memories file: {}", e))?;
-    
-    Ok(())
+
+    write_memory_store(&file_path, &memory_store)
+}
+
+/// Ranks stored memories by cosine similarity to `query`, lazily embedding
+/// (and persisting) any memory that predates the `embedding` field.
+#[tauri::command]
+async fn search_memories(
+    app: tauri::AppHandle,
+    query: String,
+    limit: usize,
+) -> Result<Vec<MemorySearchResult>, String> {
+    let file_path = get_memories_file_path(&app)?;
+    let mut memory_store = load_memory_store(&file_path)?;
+    let mut backfilled = false;
+
+    for memory in memory_store.memories.iter_mut() {
+        if memory.embedding.is_none() {
+            // Same tolerance as `save_memory`: one memory failing to embed
+            // (missing API key, rate limit, connectivity) shouldn't abort
+            // search for every other memory that already has a vector.
+            match embed_text(&memory.content).await {
+                Ok(vector) => {
+                    memory.embedding = Some(normalize(&vector));
+                    backfilled = true;
+                }
+                Err(e) => {
+                    println!("Failed to backfill embedding for memory '{}', scoring as 0.0: {}", memory.id, e);
+                }
+            }
+        }
+    }
+
+    if backfilled {
+        write_memory_store(&file_path, &memory_store)?;
+    }
+
+    let query_embedding = normalize(&embed_text(&query).await?);
+
+    let mut scored: Vec<MemorySearchResult> = memory_store
+        .memories
+        .into_iter()
+        .map(|memory| {
+            let score = memory
+                .embedding
+                .as_ref()
+                .map(|e| cosine_similarity(e, &query_embedding))
+                .unwrap_or(0.0);
+            MemorySearchResult { memory, score }
+        })
+        .collect();
+
+    scored.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    scored.truncate(limit);
+
+    Ok(scored)
 }
 
 fn main() {
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![save_memory, load_memories, delete_memory])
+        .invoke_handler(tauri::generate_handler![
+            save_memory,
+            load_memories,
+            delete_memory,
+            search_memories
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
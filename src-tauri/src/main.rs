@@ -1,9 +1,38 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use async_trait::async_trait;
+use eventsource_stream::Eventsource;
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::env;
+use std::fs;
+use std::path::PathBuf;
 use dotenvy::dotenv;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+use uuid::Uuid;
+
+const MAX_TOOL_ITERATIONS: u32 = 5;
+const SESSION_TOKEN_BUDGET: usize = 6000;
+
+const EMOTION_TAGS: &[&str] = &[
+    "happy",
+    "sad",
+    "surprised",
+    "thinking",
+    "excited",
+    "confused",
+    "neutral",
+];
+
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1/chat/completions";
+const DEFAULT_MODEL: &str = "gpt-4o-mini";
+const DEFAULT_TEMPERATURE: f32 = 0.7;
+const DEFAULT_MAX_TOKENS: u32 = 150;
+const SYSTEM_PROMPT: &str = "You are a friendly desktop robot assistant. Respond to the user's message and determine the appropriate emotion for your response. At the end of your response, add a single word indicating the emotion: [happy], [sad], [surprised], [thinking], [excited], [confused], or [neutral]. Keep responses concise and friendly.";
 
 #[derive(Debug, Serialize)]
 struct OpenAIRequest {
@@ -11,12 +40,77 @@ struct OpenAIRequest {
     messages: Vec<OpenAIMessage>,
     temperature: f32,
     max_tokens: u32,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<ToolDefinition>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct OpenAIMessage {
     role: String,
-    content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<Vec<ToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+impl OpenAIMessage {
+    fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: "system".to_string(),
+            content: Some(content.into()),
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+
+    fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: Some(content.into()),
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+
+    fn tool_result(tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            role: "tool".to_string(),
+            content: Some(content.into()),
+            tool_calls: None,
+            tool_call_id: Some(tool_call_id.into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ToolCall {
+    id: String,
+    #[serde(rename = "type")]
+    call_type: String,
+    function: ToolCallFunction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ToolCallFunction {
+    name: String,
+    arguments: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ToolDefinition {
+    #[serde(rename = "type")]
+    tool_type: String,
+    function: ToolFunctionSpec,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ToolFunctionSpec {
+    name: String,
+    description: String,
+    parameters: Value,
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,67 +123,736 @@ struct OpenAIChoice {
     message: OpenAIMessage,
 }
 
-#[tauri::command]
-async fn generate_response(user_message: String) -> Result<String, String> {
-    dotenv().ok();
-    let api_key = env::var("OPENAI_API_KEY")
-        .map_err(|_| "OPENAI_API_KEY not found in environment variables".to_string())?;
+/// One entry in `config.yaml`/`config.json`'s `clients` list.
+#[derive(Debug, Clone, Deserialize)]
+struct NamedClientConfig {
+    name: String,
+    #[serde(rename = "type")]
+    client_type: String,
+    base_url: String,
+    api_key: String,
+    #[serde(default = "default_model")]
+    model: String,
+    #[serde(default = "default_temperature")]
+    temperature: f32,
+    #[serde(default = "default_max_tokens")]
+    max_tokens: u32,
+}
 
-    println!("Received message: {}", user_message);
-    println!("Using API key: {}...", &api_key[..20]); // Log first 20 chars for debug
-
-    let client = reqwest::Client::new();
-
-    let request_body = OpenAIRequest {
-        model: "gpt-4o-mini".to_string(),
-        messages: vec![
-            OpenAIMessage {
-                role: "system".to_string(),
-                content: "You are a friendly desktop robot assistant. Respond to the user's message and determine the appropriate emotion for your response. At the end of your response, add a single word indicating the emotion: [happy], [sad], [surprised], [thinking], [excited], [confused], or [neutral]. Keep responses concise and friendly.".to_string(),
-            },
-            OpenAIMessage {
-                role: "user".to_string(),
-                content: user_message,
-            },
-        ],
-        temperature: 0.7,
-        max_tokens: 150,
+fn default_model() -> String {
+    DEFAULT_MODEL.to_string()
+}
+
+fn default_temperature() -> f32 {
+    DEFAULT_TEMPERATURE
+}
+
+fn default_max_tokens() -> u32 {
+    DEFAULT_MAX_TOKENS
+}
+
+/// Registry of named LLM clients, loaded from the app data dir so users can
+/// point the robot at Azure OpenAI, a local server, or another vendor.
+#[derive(Debug, Clone, Deserialize)]
+struct ClientConfig {
+    clients: Vec<NamedClientConfig>,
+    #[serde(default)]
+    default_client: Option<String>,
+}
+
+fn get_config_file_path(app: &tauri::AppHandle) -> Result<Option<PathBuf>, String> {
+    let app_data_dir = app
+        .path_resolver()
+        .app_data_dir()
+        .ok_or("Failed to get app data directory")?;
+
+    for file_name in ["config.yaml", "config.yml", "config.json"] {
+        let candidate = app_data_dir.join(file_name);
+        if candidate.exists() {
+            return Ok(Some(candidate));
+        }
+    }
+    Ok(None)
+}
+
+fn load_client_config(app: &tauri::AppHandle) -> Result<Option<ClientConfig>, String> {
+    let Some(file_path) = get_config_file_path(app)? else {
+        return Ok(None);
     };
 
-    println!("Sending request to OpenAI...");
-    let response = client
-        .post("https://api.openai.com/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
-
-    println!("Response status: {}", response.status());
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        println!("API error response: {}", error_text);
-        return Err(format!("OpenAI API error: {}", error_text));
-    }
-
-    let openai_response: OpenAIResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-    if let Some(choice) = openai_response.choices.first() {
-        let content = &choice.message.content;
-        println!("Received content: {}", content);
-        Ok(content.clone())
+    let file_contents = fs::read_to_string(&file_path)
+        .map_err(|e| format!("Failed to read client config: {}", e))?;
+
+    let config = if file_path.extension().and_then(|e| e.to_str()) == Some("json") {
+        serde_json::from_str(&file_contents)
+            .map_err(|e| format!("Failed to parse client config: {}", e))?
     } else {
-        Err("No response from OpenAI".to_string())
+        serde_yaml::from_str(&file_contents)
+            .map_err(|e| format!("Failed to parse client config: {}", e))?
+    };
+
+    Ok(Some(config))
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChunkChoice {
+    delta: StreamDelta,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChunk {
+    choices: Vec<StreamChunkChoice>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ResponseDonePayload {
+    content: String,
+    emotion: String,
+}
+
+/// Splits a trailing `[emotion]` tag off of a completed response, the way
+/// the frontend expects to see it split out once streaming has finished.
+fn extract_emotion_tag(text: &str) -> (String, String) {
+    let trimmed = text.trim_end();
+    if let Some(without_bracket) = trimmed.strip_suffix(']') {
+        if let Some(open) = without_bracket.rfind('[') {
+            let tag = &without_bracket[open + 1..];
+            if EMOTION_TAGS.contains(&tag) {
+                return (trimmed[..open].trim_end().to_string(), tag.to_string());
+            }
+        }
+    }
+    (text.to_string(), "neutral".to_string())
+}
+
+/// True if `text` is nothing but a single recognized `[emotion]` tag.
+fn is_emotion_tag(text: &str) -> bool {
+    let trimmed = text.trim();
+    trimmed
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .map(|tag| EMOTION_TAGS.contains(&tag))
+        .unwrap_or(false)
+}
+
+/// Mirrors the `MemoryItem`/`memories.json` shape owned by
+/// `frontend/src-tauri/src/main.rs`, so the `save_memory` tool here writes
+/// to the exact same file the `load_memories`/`search_memories` commands
+/// read from. There's no shared lib crate in this tree to hold one
+/// definition, so this struct (including the `embedding` field chunk0-1
+/// added) has to be kept by hand in sync with its counterpart — this only
+/// lands in the same `memories.json` as long as both apps' `tauri.conf.json`
+/// share the same bundle identifier/product name, since that's what Tauri's
+/// `app_data_dir()` resolves against; if they ever diverge, this tool
+/// silently starts writing to its own disconnected `memories.json`.
+#[derive(Debug, Serialize, Deserialize)]
+struct MemoryItem {
+    id: String,
+    timestamp: u64,
+    agent_type: String,
+    topic: String,
+    content: String,
+    tags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    embedding: Option<Vec<f32>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MemoryStore {
+    memories: Vec<MemoryItem>,
+}
+
+fn get_memories_file_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path_resolver()
+        .app_data_dir()
+        .ok_or("Failed to get app data directory")?;
+    fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(app_data_dir.join("memories.json"))
+}
+
+/// Tool handler signature: raw JSON arguments in, raw JSON result out.
+type ToolHandler = fn(&tauri::AppHandle, Value) -> Result<Value, String>;
+
+fn tool_save_memory(app: &tauri::AppHandle, args: Value) -> Result<Value, String> {
+    let id = args["id"].as_str().ok_or("save_memory: missing 'id'")?.to_string();
+    let topic = args["topic"].as_str().ok_or("save_memory: missing 'topic'")?.to_string();
+    let content = args["content"].as_str().ok_or("save_memory: missing 'content'")?.to_string();
+    let agent_type = args["agent_type"].as_str().unwrap_or("robot").to_string();
+    let tags = args["tags"].as_array().map(|tags| {
+        tags.iter().filter_map(|t| t.as_str().map(String::from)).collect()
+    });
+    // The tool schema doesn't ask the model for a timestamp, so this is
+    // almost always absent — fall back to now rather than 0, which would
+    // sink every tool-saved memory to the bottom of the newest-first sort.
+    let explicit_timestamp = args["timestamp"].as_u64();
+
+    let file_path = get_memories_file_path(app)?;
+    let mut memory_store = if file_path.exists() {
+        let file_contents = fs::read_to_string(&file_path)
+            .map_err(|e| format!("Failed to read memories file: {}", e))?;
+        serde_json::from_str::<MemoryStore>(&file_contents)
+            .unwrap_or_else(|_| MemoryStore { memories: vec![] })
+    } else {
+        MemoryStore { memories: vec![] }
+    };
+
+    if let Some(existing) = memory_store.memories.iter_mut().find(|m| m.id == id) {
+        // A missing timestamp on an update must not stomp the real
+        // original save time with a made-up "now".
+        if let Some(timestamp) = explicit_timestamp {
+            existing.timestamp = timestamp;
+        }
+        existing.agent_type = agent_type;
+        existing.topic = topic;
+        existing.content = content;
+        existing.tags = tags;
+        // The content just changed, so any existing embedding no longer
+        // matches it — clear it and let `search_memories`'s lazy backfill
+        // re-embed on next use.
+        existing.embedding = None;
+    } else {
+        let timestamp = explicit_timestamp.unwrap_or_else(current_timestamp);
+        memory_store.memories.push(MemoryItem {
+            id,
+            timestamp,
+            agent_type,
+            topic,
+            content,
+            tags,
+            embedding: None,
+        });
+    }
+    memory_store.memories.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+    let json = serde_json::to_string_pretty(&memory_store)
+        .map_err(|e| format!("Failed to serialize memories: {}", e))?;
+    fs::write(&file_path, json).map_err(|e| format!("Failed to write memories file: {}", e))?;
+
+    Ok(json!({ "status": "saved" }))
+}
+
+fn tool_registry() -> HashMap<&'static str, ToolHandler> {
+    let mut registry: HashMap<&'static str, ToolHandler> = HashMap::new();
+    registry.insert("save_memory", tool_save_memory);
+    registry
+}
+
+fn available_tools() -> Vec<ToolDefinition> {
+    vec![ToolDefinition {
+        tool_type: "function".to_string(),
+        function: ToolFunctionSpec {
+            name: "save_memory".to_string(),
+            description: "Persist a piece of information the user asked the robot to remember."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "id": {"type": "string", "description": "Unique identifier for this memory"},
+                    "topic": {"type": "string", "description": "Short topic/category for the memory"},
+                    "content": {"type": "string", "description": "The information to remember"},
+                    "tags": {"type": "array", "items": {"type": "string"}, "description": "Optional tags"}
+                },
+                "required": ["id", "topic", "content"]
+            }),
+        },
+    }]
+}
+
+fn dispatch_tool_call(app: &tauri::AppHandle, registry: &HashMap<&str, ToolHandler>, call: &ToolCall) -> String {
+    let Some(handler) = registry.get(call.function.name.as_str()) else {
+        return json!({ "error": format!("Unknown tool: {}", call.function.name) }).to_string();
+    };
+
+    let args: Value = serde_json::from_str(&call.function.arguments).unwrap_or(Value::Null);
+    match handler(app, args) {
+        Ok(result) => result.to_string(),
+        Err(e) => json!({ "error": e }).to_string(),
+    }
+}
+
+/// Trait-based client abstraction so new provider shapes can be added
+/// without touching the `generate_response` command handler.
+#[async_trait]
+trait LlmClient: Send + Sync {
+    /// Sends `messages` and returns the raw assistant message, which may
+    /// carry `tool_calls` instead of (or alongside) text content.
+    async fn complete_raw(
+        &self,
+        messages: &[OpenAIMessage],
+        tools: &[ToolDefinition],
+    ) -> Result<OpenAIMessage, String>;
+
+    async fn complete(&self, messages: &[OpenAIMessage]) -> Result<String, String> {
+        let message = self.complete_raw(messages, &[]).await?;
+        Ok(message.content.unwrap_or_default())
+    }
+
+    async fn stream_complete(
+        &self,
+        messages: &[OpenAIMessage],
+        app: &tauri::AppHandle,
+    ) -> Result<(), String>;
+}
+
+/// Handles any vendor that speaks the OpenAI chat-completions wire format:
+/// OpenAI itself, Azure OpenAI, Ollama, LM Studio, etc.
+struct OpenAiCompatibleClient {
+    base_url: String,
+    api_key: String,
+    model: String,
+    temperature: f32,
+    max_tokens: u32,
+}
+
+impl OpenAiCompatibleClient {
+    fn default_openai() -> Result<Self, String> {
+        dotenv().ok();
+        let api_key = env::var("OPENAI_API_KEY")
+            .map_err(|_| "OPENAI_API_KEY not found in environment variables".to_string())?;
+
+        Ok(Self {
+            base_url: DEFAULT_OPENAI_BASE_URL.to_string(),
+            api_key,
+            model: DEFAULT_MODEL.to_string(),
+            temperature: DEFAULT_TEMPERATURE,
+            max_tokens: DEFAULT_MAX_TOKENS,
+        })
+    }
+
+    fn from_config(config: NamedClientConfig) -> Self {
+        Self {
+            base_url: config.base_url,
+            api_key: config.api_key,
+            model: config.model,
+            temperature: config.temperature,
+            max_tokens: config.max_tokens,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmClient for OpenAiCompatibleClient {
+    async fn complete_raw(
+        &self,
+        messages: &[OpenAIMessage],
+        tools: &[ToolDefinition],
+    ) -> Result<OpenAIMessage, String> {
+        let client = reqwest::Client::new();
+
+        let request_body = OpenAIRequest {
+            model: self.model.clone(),
+            messages: messages.to_vec(),
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            stream: false,
+            tools: if tools.is_empty() { None } else { Some(tools.to_vec()) },
+        };
+
+        let response = client
+            .post(&self.base_url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(format!("LLM provider error: {}", error_text));
+        }
+
+        let openai_response: OpenAIResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+        openai_response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message)
+            .ok_or_else(|| "No response from LLM provider".to_string())
+    }
+
+    async fn stream_complete(
+        &self,
+        messages: &[OpenAIMessage],
+        app: &tauri::AppHandle,
+    ) -> Result<(), String> {
+        let client = reqwest::Client::new();
+
+        let request_body = OpenAIRequest {
+            model: self.model.clone(),
+            messages: messages.to_vec(),
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            stream: true,
+            tools: None,
+        };
+
+        let response = client
+            .post(&self.base_url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(format!("LLM provider error: {}", error_text));
+        }
+
+        let mut event_stream = response.bytes_stream().eventsource();
+        let mut full_content = String::new();
+        // Holds text from the last "[" of the first chunk that contains one
+        // onward, in case it's the start of a trailing "[emotion]" tag. Once we start holding we
+        // never flush it early — not even once the bracket closes — since
+        // we can't be sure it's the trailing tag until the stream ends.
+        let mut pending_tail = String::new();
+        let mut holding = false;
+
+        while let Some(event) = event_stream.next().await {
+            let event = event.map_err(|e| format!("Stream error: {}", e))?;
+            if event.data == "[DONE]" {
+                break;
+            }
+
+            let chunk: StreamChunk = serde_json::from_str(&event.data)
+                .map_err(|e| format!("Failed to parse stream chunk: {}", e))?;
+
+            let Some(delta) = chunk.choices.first().and_then(|c| c.delta.content.clone()) else {
+                continue;
+            };
+
+            full_content.push_str(&delta);
+            pending_tail.push_str(&delta);
+
+            if holding {
+                continue;
+            }
+
+            match pending_tail.rfind('[') {
+                Some(open) => {
+                    holding = true;
+                    let safe_to_emit = pending_tail[..open].to_string();
+                    if !safe_to_emit.is_empty() {
+                        app.emit_all("response-chunk", safe_to_emit).ok();
+                    }
+                    pending_tail = pending_tail[open..].to_string();
+                }
+                None => {
+                    app.emit_all("response-chunk", pending_tail.clone()).ok();
+                    pending_tail.clear();
+                }
+            }
+        }
+
+        // The stream is done: only now decide whether what we held back is
+        // really the trailing emotion tag (dropped, since response-done
+        // carries it separately) or just ordinary trailing content.
+        if !pending_tail.is_empty() && !is_emotion_tag(&pending_tail) {
+            let (held_content, _) = extract_emotion_tag(&pending_tail);
+            app.emit_all("response-chunk", held_content).ok();
+        }
+
+        let (content, emotion) = extract_emotion_tag(&full_content);
+        app.emit_all("response-done", ResponseDonePayload { content, emotion }).ok();
+
+        Ok(())
+    }
+}
+
+fn build_client(app: &tauri::AppHandle, client_name: Option<String>) -> Result<Box<dyn LlmClient>, String> {
+    let config = load_client_config(app)?;
+
+    let Some(config) = config else {
+        // An explicitly requested client with no config file at all must
+        // fail the same way as an explicitly requested client missing from
+        // an existing config, rather than silently falling back to OpenAI.
+        return match client_name {
+            Some(name) => Err(format!("No client named '{}' in config", name)),
+            None => Ok(Box::new(OpenAiCompatibleClient::default_openai()?)),
+        };
+    };
+
+    let wanted_name = client_name.or_else(|| config.default_client.clone());
+
+    let named_config = match wanted_name {
+        Some(name) => config
+            .clients
+            .into_iter()
+            .find(|c| c.name == name)
+            .ok_or_else(|| format!("No client named '{}' in config", name))?,
+        None => config
+            .clients
+            .into_iter()
+            .next()
+            .ok_or_else(|| "No clients configured".to_string())?,
+    };
+
+    match named_config.client_type.as_str() {
+        "openai" | "azure-openai" | "ollama" | "lm-studio" => {
+            Ok(Box::new(OpenAiCompatibleClient::from_config(named_config)))
+        }
+        other => Err(format!("Unsupported client type: {}", other)),
+    }
+}
+
+/// Runs the chat + tool-calling loop against `messages` in place: send the
+/// conversation, and whenever the model answers with `tool_calls` instead
+/// of text, dispatch each call to its registered handler, feed the results
+/// back as `role: "tool"` messages, and ask again. Capped at
+/// `MAX_TOOL_ITERATIONS` to prevent the model from cycling on tool calls
+/// forever. Returns the final assistant text; `messages` ends up holding
+/// the full exchange, including any intermediate tool turns.
+async fn run_tool_loop(
+    client: &dyn LlmClient,
+    app: &tauri::AppHandle,
+    messages: &mut Vec<OpenAIMessage>,
+    tools: &[ToolDefinition],
+    registry: &HashMap<&str, ToolHandler>,
+) -> Result<String, String> {
+    for _ in 0..MAX_TOOL_ITERATIONS {
+        let assistant_message = client.complete_raw(messages, tools).await?;
+
+        let Some(tool_calls) = assistant_message.tool_calls.clone() else {
+            let content = assistant_message.content.clone().unwrap_or_default();
+            messages.push(assistant_message);
+            return Ok(content);
+        };
+
+        messages.push(assistant_message);
+        for call in &tool_calls {
+            let result = dispatch_tool_call(app, registry, call);
+            messages.push(OpenAIMessage::tool_result(call.id.clone(), result));
+        }
+    }
+
+    Err("Tool-calling loop exceeded maximum iterations".to_string())
+}
+
+#[tauri::command]
+async fn generate_response(
+    app: tauri::AppHandle,
+    user_message: String,
+    client_name: Option<String>,
+) -> Result<String, String> {
+    println!("Received message: {}", user_message);
+
+    let client = build_client(&app, client_name)?;
+    let tools = available_tools();
+    let registry = tool_registry();
+
+    let mut messages = vec![OpenAIMessage::system(SYSTEM_PROMPT), OpenAIMessage::user(user_message)];
+
+    run_tool_loop(client.as_ref(), &app, &mut messages, &tools, &registry).await
+}
+
+/// Streaming variant of `generate_response`. Emits incremental
+/// `response-chunk` events as tokens arrive, followed by one
+/// `response-done` event carrying the full text and parsed emotion tag.
+#[tauri::command]
+async fn generate_response_stream(
+    app: tauri::AppHandle,
+    user_message: String,
+    client_name: Option<String>,
+) -> Result<(), String> {
+    println!("Received message (stream): {}", user_message);
+
+    let client = build_client(&app, client_name)?;
+    let messages = vec![OpenAIMessage::system(SYSTEM_PROMPT), OpenAIMessage::user(user_message)];
+
+    client.stream_complete(&messages, &app).await
+}
+
+/// A persisted multi-turn conversation, stored at
+/// `sessions/<id>.json` in the app data dir.
+#[derive(Debug, Serialize, Deserialize)]
+struct Session {
+    id: String,
+    messages: Vec<OpenAIMessage>,
+    created_at: u64,
+}
+
+fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn get_sessions_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path_resolver()
+        .app_data_dir()
+        .ok_or("Failed to get app data directory")?;
+    let sessions_dir = app_data_dir.join("sessions");
+    fs::create_dir_all(&sessions_dir)
+        .map_err(|e| format!("Failed to create sessions directory: {}", e))?;
+    Ok(sessions_dir)
+}
+
+fn get_session_file_path(app: &tauri::AppHandle, session_id: &str) -> Result<PathBuf, String> {
+    Ok(get_sessions_dir(app)?.join(format!("{}.json", session_id)))
+}
+
+fn load_session(app: &tauri::AppHandle, session_id: &str) -> Result<Session, String> {
+    let file_path = get_session_file_path(app, session_id)?;
+    let file_contents = fs::read_to_string(&file_path)
+        .map_err(|e| format!("Failed to read session '{}': {}", session_id, e))?;
+    serde_json::from_str(&file_contents)
+        .map_err(|e| format!("Failed to parse session '{}': {}", session_id, e))
+}
+
+fn save_session(app: &tauri::AppHandle, session: &Session) -> Result<(), String> {
+    let file_path = get_session_file_path(app, &session.id)?;
+    let json = serde_json::to_string_pretty(session)
+        .map_err(|e| format!("Failed to serialize session: {}", e))?;
+    fs::write(&file_path, json).map_err(|e| format!("Failed to write session: {}", e))
+}
+
+/// Rough token estimate (~4 chars/token) since we don't pull in a tokenizer
+/// just to keep sessions under budget.
+fn estimate_tokens(text: &str) -> usize {
+    (text.len() / 4).max(1)
+}
+
+/// Token estimate for a whole message, including `tool_calls` arguments —
+/// an assistant message that only carries `tool_calls` has `content: None`
+/// but its arguments still count against the budget.
+fn estimate_message_tokens(message: &OpenAIMessage) -> usize {
+    let content_tokens = message.content.as_deref().map(estimate_tokens).unwrap_or(0);
+    let tool_call_tokens: usize = message
+        .tool_calls
+        .as_ref()
+        .map(|calls| calls.iter().map(|c| estimate_tokens(&c.function.arguments)).sum())
+        .unwrap_or(0);
+    content_tokens + tool_call_tokens
+}
+
+/// Trims the oldest non-system messages until the session's estimated
+/// token count fits within `SESSION_TOKEN_BUDGET`, so long chats stay
+/// within the model's context window.
+///
+/// An assistant `tool_calls` message and the `role: "tool"` messages that
+/// answer it must be evicted together — splitting the pair leaves an
+/// orphaned `tool` message with no matching `tool_calls`, which the
+/// chat-completions API rejects on the next `send_to_session` call,
+/// permanently bricking the session once that's persisted.
+fn trim_session_history(messages: &mut Vec<OpenAIMessage>) {
+    let mut total: usize = messages.iter().map(estimate_message_tokens).sum();
+
+    while total > SESSION_TOKEN_BUDGET {
+        let Some(unit_start) = messages.iter().position(|m| m.role != "system") else {
+            break;
+        };
+
+        let mut unit_end = unit_start + 1;
+        if messages[unit_start].tool_calls.is_some() {
+            while unit_end < messages.len() && messages[unit_end].role == "tool" {
+                unit_end += 1;
+            }
+        }
+
+        let removed_tokens: usize = messages[unit_start..unit_end]
+            .iter()
+            .map(estimate_message_tokens)
+            .sum();
+        messages.drain(unit_start..unit_end);
+        total = total.saturating_sub(removed_tokens);
+    }
+}
+
+#[tauri::command]
+fn start_session(app: tauri::AppHandle) -> Result<Session, String> {
+    let session = Session {
+        id: Uuid::new_v4().to_string(),
+        messages: vec![OpenAIMessage::system(SYSTEM_PROMPT)],
+        created_at: current_timestamp(),
+    };
+    save_session(&app, &session)?;
+    Ok(session)
+}
+
+/// Appends `user_message` to the session, sends the full history (so the
+/// robot has memory of the conversation within this chat), appends the
+/// assistant's reply, trims the history to budget, and persists it.
+#[tauri::command]
+async fn send_to_session(
+    app: tauri::AppHandle,
+    session_id: String,
+    user_message: String,
+    client_name: Option<String>,
+) -> Result<String, String> {
+    let mut session = load_session(&app, &session_id)?;
+    session.messages.push(OpenAIMessage::user(user_message));
+
+    let client = build_client(&app, client_name)?;
+    let tools = available_tools();
+    let registry = tool_registry();
+
+    let reply = run_tool_loop(client.as_ref(), &app, &mut session.messages, &tools, &registry).await?;
+
+    trim_session_history(&mut session.messages);
+    save_session(&app, &session)?;
+
+    Ok(reply)
+}
+
+#[tauri::command]
+fn list_sessions(app: tauri::AppHandle) -> Result<Vec<Session>, String> {
+    let sessions_dir = get_sessions_dir(&app)?;
+    let mut sessions = vec![];
+
+    for entry in fs::read_dir(&sessions_dir)
+        .map_err(|e| format!("Failed to read sessions directory: {}", e))?
+    {
+        let entry = entry.map_err(|e| format!("Failed to read sessions directory entry: {}", e))?;
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let file_contents = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read session file: {}", e))?;
+        if let Ok(session) = serde_json::from_str::<Session>(&file_contents) {
+            sessions.push(session);
+        }
+    }
+
+    sessions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(sessions)
+}
+
+#[tauri::command]
+fn delete_session(app: tauri::AppHandle, session_id: String) -> Result<(), String> {
+    let file_path = get_session_file_path(&app, &session_id)?;
+    if file_path.exists() {
+        fs::remove_file(&file_path).map_err(|e| format!("Failed to delete session: {}", e))?;
     }
+    Ok(())
 }
 
 fn main() {
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![generate_response])
+        .invoke_handler(tauri::generate_handler![
+            generate_response,
+            generate_response_stream,
+            start_session,
+            send_to_session,
+            list_sessions,
+            delete_session
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }